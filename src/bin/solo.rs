@@ -1,27 +1,108 @@
+use std::path::PathBuf;
+
 use clap::Parser;
+use rand::{rngs::StdRng, SeedableRng};
 
-use solo_ttrpg_helper::dice::Dice;
+use solo_ttrpg_helper::dice::{Bindings, Dice, Pool};
 
 #[derive(Debug, clap::Subcommand)]
-#[clap(trailing_var_arg = true)]
 enum Command {
-    #[clap(alias("r"), trailing_var_arg = true)]
-    Roll { dice_spec: Vec<String> },
+    #[clap(alias("r"))]
+    Roll {
+        /// The roll expression, e.g. `d20 + STR`. Quote it if it contains
+        /// spaces or a negative modifier like `-2`, so it isn't mistaken
+        /// for a flag (and so `--seed` can still appear after it).
+        dice_spec: Vec<String>,
+    },
+    #[clap(alias("p"))]
+    Pool {
+        /// The pool spec, e.g. `5d10t7`. Quote it if it contains spaces.
+        pool_spec: Vec<String>,
+    },
+    /// Set a named binding (e.g. an ability score) for use in future rolls.
+    #[clap(alias("bind"))]
+    Set {
+        /// The binding's name, e.g. `STR`.
+        name: String,
+        /// The value to bind `name` to.
+        value: i32,
+    },
 }
 
 #[derive(Debug, clap::Parser)]
 struct CLI {
     #[clap(subcommand)]
     subcommand: Command,
+
+    /// Seed the RNG for a reproducible roll, e.g. for replaying a session.
+    /// Omit for a randomly-seeded roll.
+    #[clap(long, global = true)]
+    seed: Option<u64>,
+}
+
+fn rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// Where per-player bindings (ability scores, proficiency bonus, etc.) are
+/// persisted between `sttrpg` invocations.
+fn bindings_path() -> PathBuf {
+    let home = std::env::var("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("."));
+    home.join(".sttrpg_bindings")
+}
+
+/// Loads `name=value` bindings from [`bindings_path`], ignoring any line
+/// that isn't in that shape. Missing file means no bindings are set.
+fn load_bindings() -> Bindings {
+    let contents = std::fs::read_to_string(bindings_path()).unwrap_or_default();
+    contents
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .filter_map(|(name, value)| value.trim().parse().ok().map(|v| (name.trim().into(), v)))
+        .collect()
+}
+
+/// Sets `name` to `value` in [`bindings_path`], overwriting any existing
+/// binding for `name` and leaving the rest of the file untouched.
+fn save_binding(name: &str, value: i32) -> std::io::Result<()> {
+    let path = bindings_path();
+    let contents = std::fs::read_to_string(&path).unwrap_or_default();
+    let mut lines: Vec<String> = contents
+        .lines()
+        .filter(|line| line.split_once('=').map(|(n, _)| n.trim()) != Some(name))
+        .map(String::from)
+        .collect();
+    lines.push(format!("{}={}", name, value));
+    std::fs::write(path, lines.join("\n") + "\n")
 }
 
 fn main() {
     let cli = CLI::parse();
+    let mut rng = rng(cli.seed);
     match cli.subcommand {
         Command::Roll { dice_spec } => {
             let s = dice_spec.join(" ");
             let dice: Dice = s.parse().unwrap();
-            println!("{}", dice.roll());
+            let bindings = load_bindings();
+            match dice.roll_with(&bindings, &mut rng) {
+                Ok(result) => println!("{}", result),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+        Command::Pool { pool_spec } => {
+            let s = pool_spec.join(" ");
+            let pool: Pool = s.parse().unwrap();
+            println!("{}", pool.roll_with(&mut rng));
+        }
+        Command::Set { name, value } => {
+            if let Err(e) = save_binding(&name, value) {
+                eprintln!("Could not save binding: {}", e);
+            }
         }
     }
 }
@@ -1,4 +1,4 @@
-use std::{fmt::Display, str::FromStr};
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
 
 use rand::Rng;
 use thiserror::Error;
@@ -7,20 +7,50 @@ type Modifier = i32;
 type Sides = u16;
 type SignedSides = i32;
 
+/// A single roll of an exploding group (or pool) adds at most this many
+/// extra dice in total, so a degenerate spec like `d1!` can't loop forever.
+const MAX_EXPLOSIONS: usize = 100;
+
+/// Above this many dice, [`Dice::distribution`] still computes an exact
+/// result, but warns since the outcome range (and so the work involved)
+/// grows linearly with it.
+const MAX_DICE_FOR_QUIET_DISTRIBUTION: usize = 10_000;
+
 #[derive(Debug, Error)]
 pub enum DiceError {
     #[error("Could not understand roll: {0}")]
     Unparseable(String),
+    #[error("No value bound for variable: {0}")]
+    VariableNotFound(String),
+    #[error("Division by zero")]
+    DivisionByZero,
+    #[error("Result is too large to represent")]
+    Overflow,
+    #[error("Can't compute an exact distribution for {0}: keep/drop and exploding dice aren't modeled")]
+    UnsupportedDistribution(String),
 }
 
+/// A target number that a pool die must meet or exceed to count as a
+/// success, e.g. the `7` in `5d10t7`.
+type Target = Sides;
+
+/// Named values (ability scores, proficiency bonus, etc.) that a roll
+/// expression can reference by identifier, e.g. `d20 + STR`.
+pub type Bindings = std::collections::HashMap<String, Modifier>;
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Die {
     pub sides: Sides,
 }
 
 impl Die {
+    /// Rolls using `rng`, so a seeded RNG gives reproducible results.
+    pub fn roll_with(&self, rng: &mut impl Rng) -> Sides {
+        rng.gen_range(1..=self.sides)
+    }
+
     pub fn roll(&self) -> Sides {
-        rand::thread_rng().gen_range(1..=self.sides)
+        self.roll_with(&mut rand::thread_rng())
     }
 }
 
@@ -47,30 +77,158 @@ impl FromStr for Die {
     }
 }
 
+/// Which dice in a group are kept after rolling; the rest are marked as
+/// dropped and excluded from the total.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KeepRule {
+    KeepHighest(usize),
+    KeepLowest(usize),
+    DropHighest(usize),
+    DropLowest(usize),
+}
+
+/// A die re-rolls and adds another die to the group whenever it shows its
+/// max value, or (if `threshold` is set) whenever it beats that threshold.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ExplodeRule {
+    pub threshold: Option<Sides>,
+}
+
+/// A single `NdS` term in a roll expression, plus any keep/drop or
+/// exploding modifiers attached to it (e.g. `4d6kh3`, `d10!>8`).
 #[derive(Clone, Debug, Eq, PartialEq)]
-enum DiceSpecPart {
-    Die { die: Die, count: usize },
-    Modifier(Modifier),
+pub struct DieGroup {
+    sides: Sides,
+    count: usize,
+    keep: Option<KeepRule>,
+    explode: Option<ExplodeRule>,
 }
 
-impl FromStr for DiceSpecPart {
-    type Err = DiceError;
+impl DieGroup {
+    /// A group with no keep/drop or exploding modifiers, e.g. plain `2d4`.
+    fn plain(sides: Sides, count: usize) -> Self {
+        DieGroup {
+            sides,
+            count,
+            keep: None,
+            explode: None,
+        }
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.contains('d') {
-            let parts: Vec<_> = s.split('d').collect();
-            if parts.len() != 2 {
-                return Err(DiceError::Unparseable(s.into()));
+impl Display for DieGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.count, Die { sides: self.sides })?;
+        if let Some(explode) = self.explode {
+            write!(f, "!")?;
+            if let Some(threshold) = explode.threshold {
+                write!(f, ">{}", threshold)?;
             }
-            let count: usize = if parts[0].is_empty() {
+        }
+        match self.keep {
+            Some(KeepRule::KeepHighest(n)) => write!(f, "kh{}", n)?,
+            Some(KeepRule::KeepLowest(n)) => write!(f, "kl{}", n)?,
+            Some(KeepRule::DropHighest(n)) => write!(f, "dh{}", n)?,
+            Some(KeepRule::DropLowest(n)) => write!(f, "dl{}", n)?,
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+/// Parses the `kh3` / `kl1` / `dh1` / `dl1` suffix, if present, returning the
+/// rule and whatever of `s` is left over.
+fn parse_keep(s: &str) -> Result<(Option<KeepRule>, &str), DiceError> {
+    for (prefix, ctor) in [
+        ("kh", KeepRule::KeepHighest as fn(usize) -> KeepRule),
+        ("kl", KeepRule::KeepLowest as fn(usize) -> KeepRule),
+        ("dh", KeepRule::DropHighest as fn(usize) -> KeepRule),
+        ("dl", KeepRule::DropLowest as fn(usize) -> KeepRule),
+    ] {
+        if let Some(rest) = s.strip_prefix(prefix) {
+            let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+            let n: usize = if digits.is_empty() {
                 1
             } else {
-                parts[0]
+                digits
                     .parse()
                     .map_err(|_| DiceError::Unparseable(s.into()))?
             };
-            let die: Die = format!("d{}", parts[1]).parse()?;
-            Ok(Self::Die { die, count })
+            return Ok((Some(ctor(n)), &rest[digits.len()..]));
+        }
+    }
+    Ok((None, s))
+}
+
+/// Parses the `!` / `!>8` exploding suffix, if present, returning the rule
+/// and whatever of `s` is left over.
+fn parse_explode(s: &str) -> Result<(Option<ExplodeRule>, &str), DiceError> {
+    match s.strip_prefix('!') {
+        None => Ok((None, s)),
+        Some(rest) => {
+            if let Some(rest) = rest.strip_prefix('>') {
+                let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+                if digits.is_empty() {
+                    return Err(DiceError::Unparseable(s.into()));
+                }
+                let threshold: Sides = digits
+                    .parse()
+                    .map_err(|_| DiceError::Unparseable(s.into()))?;
+                Ok((
+                    Some(ExplodeRule {
+                        threshold: Some(threshold),
+                    }),
+                    &rest[digits.len()..],
+                ))
+            } else {
+                Ok((Some(ExplodeRule { threshold: None }), rest))
+            }
+        }
+    }
+}
+
+/// Parses a single `NdS` term, e.g. `4d6kh3`, `d10!`, or `d10!>8`.
+fn parse_die_group(s: &str) -> Result<DieGroup, DiceError> {
+    let err = || DiceError::Unparseable(s.into());
+    let d_pos = s.find('d').ok_or_else(err)?;
+    let count: usize = if d_pos == 0 {
+        1
+    } else {
+        s[..d_pos].parse().map_err(|_| err())?
+    };
+    let after_d = &s[d_pos + 1..];
+    let sides_digits: String = after_d.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if sides_digits.is_empty() {
+        return Err(err());
+    }
+    let sides: Sides = sides_digits.parse().map_err(|_| err())?;
+    let rest = &after_d[sides_digits.len()..];
+
+    let (explode, rest) = parse_explode(rest)?;
+    let (keep, rest) = parse_keep(rest)?;
+    if !rest.is_empty() {
+        return Err(err());
+    }
+    Ok(DieGroup {
+        sides,
+        count,
+        keep,
+        explode,
+    })
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum DiceSpecPart {
+    Die(DieGroup),
+    Modifier(Modifier),
+}
+
+impl FromStr for DiceSpecPart {
+    type Err = DiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains('d') {
+            Ok(Self::Die(parse_die_group(s)?))
         } else {
             Ok(Self::Modifier(
                 s.parse().map_err(|_| DiceError::Unparseable(s.into()))?,
@@ -79,41 +237,416 @@ impl FromStr for DiceSpecPart {
     }
 }
 
+/// An arithmetic operator joining two terms of a roll expression.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::Div => "/",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// A node in a roll expression tree: a die group, a plain number, or a
+/// binary operation combining two sub-expressions.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Expr {
+    Group(DieGroup),
+    Number(Modifier),
+    Variable(String),
+    BinOp(Box<Expr>, Op, Box<Expr>),
+}
+
+/// Variable names are identifiers: they start with a letter or underscore
+/// and otherwise contain only alphanumerics or underscores.
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    Op(char),
+    LParen,
+    RParen,
+}
+
+/// Splits a roll expression into words (die specs and numbers), operators,
+/// and parens. Words are any run of characters other than whitespace,
+/// `+-*/()`, so grammar like `kh3` or `!>8` stays attached to its die spec.
+fn tokenize(s: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i] as char;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if "+-*/".contains(c) {
+            tokens.push(Token::Op(c));
+            i += 1;
+        } else {
+            let start = i;
+            while i < bytes.len() && !(bytes[i] as char).is_whitespace() && !"+-*/()".contains(bytes[i] as char)
+            {
+                i += 1;
+            }
+            tokens.push(Token::Word(&s[start..i]));
+        }
+    }
+    tokens
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<Token<'a>> {
+        let t = self.peek();
+        self.pos += 1;
+        t
+    }
+
+    /// expr := term (('+' | '-') term)*
+    fn parse_expr(&mut self) -> Result<Expr, DiceError> {
+        let mut left = self.parse_term()?;
+        loop {
+            match self.peek() {
+                Some(Token::Op(op @ ('+' | '-'))) => {
+                    self.bump();
+                    let right = self.parse_term()?;
+                    let op = if op == '+' { Op::Add } else { Op::Sub };
+                    left = Expr::BinOp(Box::new(left), op, Box::new(right));
+                }
+                _ => return Ok(left),
+            }
+        }
+    }
+
+    /// term := factor (('*' | '/') factor)*
+    fn parse_term(&mut self) -> Result<Expr, DiceError> {
+        let mut left = self.parse_factor()?;
+        loop {
+            match self.peek() {
+                Some(Token::Op(op @ ('*' | '/'))) => {
+                    self.bump();
+                    let right = self.parse_factor()?;
+                    let op = if op == '*' { Op::Mul } else { Op::Div };
+                    left = Expr::BinOp(Box::new(left), op, Box::new(right));
+                }
+                _ => return Ok(left),
+            }
+        }
+    }
+
+    /// factor := '-' factor | '(' expr ')' | word
+    fn parse_factor(&mut self) -> Result<Expr, DiceError> {
+        match self.bump() {
+            Some(Token::Op('-')) => {
+                let inner = self.parse_factor()?;
+                Ok(Expr::BinOp(
+                    Box::new(Expr::Number(0)),
+                    Op::Sub,
+                    Box::new(inner),
+                ))
+            }
+            Some(Token::LParen) => {
+                let inner = self.parse_expr()?;
+                match self.bump() {
+                    Some(Token::RParen) => Ok(inner),
+                    _ => Err(DiceError::Unparseable("expected a closing )".into())),
+                }
+            }
+            Some(Token::Word(word)) => match word.parse::<DiceSpecPart>() {
+                Ok(DiceSpecPart::Die(group)) => Ok(Expr::Group(group)),
+                Ok(DiceSpecPart::Modifier(m)) => Ok(Expr::Number(m)),
+                Err(e) => {
+                    if is_identifier(word) {
+                        Ok(Expr::Variable(word.to_string()))
+                    } else {
+                        Err(e)
+                    }
+                }
+            },
+            _ => Err(DiceError::Unparseable("unexpected end of expression".into())),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Dice {
-    counts: Vec<(Sides, usize)>,
-    modifier: Option<Modifier>,
+    expr: Expr,
 }
 
 impl Dice {
     pub fn new(counts: &[(Sides, usize)], modifier: Option<i32>) -> Dice {
-        let mut sorted = counts.to_vec();
-        sorted.sort_by_key(|pair| -(pair.0 as SignedSides));
-        Dice {
-            counts: sorted,
-            modifier,
+        let mut sorted: Vec<DieGroup> = counts
+            .iter()
+            .map(|&(sides, count)| DieGroup::plain(sides, count))
+            .collect();
+        sorted.sort_by_key(|group| -(group.sides as SignedSides));
+
+        let groups_expr = sorted
+            .into_iter()
+            .map(Expr::Group)
+            .reduce(|a, b| Expr::BinOp(Box::new(a), Op::Add, Box::new(b)));
+        let expr = match (groups_expr, modifier) {
+            (Some(e), Some(m)) => Expr::BinOp(Box::new(e), Op::Add, Box::new(Expr::Number(m))),
+            (Some(e), None) => e,
+            (None, Some(m)) => Expr::Number(m),
+            (None, None) => Expr::Number(0),
+        };
+        Dice { expr }
+    }
+
+    pub fn num_dice(&self) -> usize {
+        fn count(expr: &Expr) -> usize {
+            match expr {
+                Expr::Number(_) | Expr::Variable(_) => 0,
+                Expr::Group(group) => group.count,
+                Expr::BinOp(l, _, r) => count(l) + count(r),
+            }
         }
+        count(&self.expr)
     }
 
-    pub fn counts(&self) -> &[(Sides, usize)] {
-        &self.counts
+    /// Computes the exact probability of every possible total, without
+    /// sampling. Keep/drop and exploding modifiers would need per-die order
+    /// statistics rather than a plain convolution, which isn't implemented
+    /// yet, so this returns [`DiceError::UnsupportedDistribution`] for any
+    /// group carrying them rather than silently reporting the distribution
+    /// of an unmodified group.
+    pub fn distribution(&self, bindings: &Bindings) -> Result<Distribution, DiceError> {
+        check_distribution_supported(&self.expr)?;
+        if self.num_dice() > MAX_DICE_FOR_QUIET_DISTRIBUTION {
+            eprintln!(
+                "warning: computing an exact distribution over {} dice, this may be slow",
+                self.num_dice()
+            );
+        }
+        expr_distribution(&self.expr, bindings)
     }
 
-    pub fn num_dice(&self) -> usize {
-        self.counts.iter().map(|it| it.1).sum()
+    /// Rolls the dice using `rng`, resolving any variables against
+    /// `bindings`. A seeded `rng` gives reproducible results.
+    pub fn roll_with(
+        &self,
+        bindings: &Bindings,
+        rng: &mut impl Rng,
+    ) -> Result<RollResult, DiceError> {
+        let root = roll_expr(&self.expr, bindings, rng)?;
+        let total = eval_node(&root)?;
+        Ok(RollResult { root, total })
+    }
+
+    /// Rolls the dice, resolving any variables against `bindings`.
+    pub fn roll(&self, bindings: &Bindings) -> Result<RollResult, DiceError> {
+        self.roll_with(bindings, &mut rand::thread_rng())
+    }
+}
+
+fn roll_expr(expr: &Expr, bindings: &Bindings, rng: &mut impl Rng) -> Result<EvalNode, DiceError> {
+    match expr {
+        Expr::Number(n) => Ok(EvalNode::Number(*n)),
+        Expr::Variable(name) => {
+            let value = *bindings
+                .get(name)
+                .ok_or_else(|| DiceError::VariableNotFound(name.clone()))?;
+            Ok(EvalNode::Variable(name.clone(), value))
+        }
+        Expr::Group(group) => Ok(EvalNode::Group(roll_group(group, rng))),
+        Expr::BinOp(l, op, r) => Ok(EvalNode::BinOp(
+            Box::new(roll_expr(l, bindings, rng)?),
+            *op,
+            Box::new(roll_expr(r, bindings, rng)?),
+        )),
+    }
+}
+
+/// Rolls every die in `group` using `rng`, applying its exploding and
+/// keep/drop rules.
+fn roll_group(group: &DieGroup, rng: &mut impl Rng) -> Vec<RolledDie> {
+    let die = Die { sides: group.sides };
+    let mut rolls = Vec::with_capacity(group.count);
+    for _ in 0..group.count {
+        rolls.push(RolledDie {
+            die,
+            value: die.roll_with(rng),
+            exploded: false,
+            dropped: false,
+        });
+    }
+
+    if let Some(explode) = group.explode {
+        let mut i = 0;
+        let mut explosions = 0;
+        while i < rolls.len() {
+            let triggers = match explode.threshold {
+                Some(threshold) => rolls[i].value > threshold,
+                None => rolls[i].value == group.sides,
+            };
+            if triggers && explosions < MAX_EXPLOSIONS {
+                rolls[i].exploded = true;
+                rolls.push(RolledDie {
+                    die,
+                    value: die.roll_with(rng),
+                    exploded: false,
+                    dropped: false,
+                });
+                explosions += 1;
+            }
+            i += 1;
+        }
+    }
+
+    if let Some(keep) = group.keep {
+        let mut by_value: Vec<usize> = (0..rolls.len()).collect();
+        by_value.sort_by_key(|&i| rolls[i].value);
+        let n = by_value.len();
+        let to_drop: &[usize] = match keep {
+            KeepRule::KeepHighest(k) => &by_value[..n.saturating_sub(k)],
+            KeepRule::KeepLowest(k) => &by_value[k.min(n)..],
+            KeepRule::DropHighest(k) => &by_value[n.saturating_sub(k)..],
+            KeepRule::DropLowest(k) => &by_value[..k.min(n)],
+        };
+        for &i in to_drop {
+            rolls[i].dropped = true;
+        }
+    }
+
+    rolls
+}
+
+/// A probability distribution over integer roll outcomes, e.g. as returned
+/// by [`Dice::distribution`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution {
+    probabilities: BTreeMap<SignedSides, f64>,
+}
+
+impl Distribution {
+    fn point_mass(value: SignedSides) -> Distribution {
+        Distribution {
+            probabilities: BTreeMap::from([(value, 1.0)]),
+        }
+    }
+
+    fn uniform_die(sides: Sides) -> Distribution {
+        let p = 1.0 / sides as f64;
+        Distribution {
+            probabilities: (1..=sides).map(|v| (v as SignedSides, p)).collect(),
+        }
     }
 
-    pub fn roll(&self) -> RollResult {
-        let mut outcomes = Vec::with_capacity(self.num_dice());
-        for (sides, count) in self.counts.iter() {
-            let die = Die { sides: *sides };
-            for _ in 0..*count {
-                outcomes.push((die, die.roll()));
+    /// Discrete convolution: for every pair of outcomes `(a, b)` from `self`
+    /// and `other`, accumulates `a op b` with probability `p(a) * p(b)`.
+    /// Errors if `op` is division and some pair divides by zero, or if some
+    /// pair's result overflows, since neither has an integer result to
+    /// place in the distribution.
+    fn convolve(&self, other: &Distribution, op: Op) -> Result<Distribution, DiceError> {
+        let mut probabilities: BTreeMap<SignedSides, f64> = BTreeMap::new();
+        for (&a, &pa) in &self.probabilities {
+            for (&b, &pb) in &other.probabilities {
+                let outcome = match op {
+                    Op::Add => a.checked_add(b),
+                    Op::Sub => a.checked_sub(b),
+                    Op::Mul => a.checked_mul(b),
+                    Op::Div if b == 0 => return Err(DiceError::DivisionByZero),
+                    Op::Div => a.checked_div(b),
+                }
+                .ok_or(DiceError::Overflow)?;
+                *probabilities.entry(outcome).or_insert(0.0) += pa * pb;
             }
         }
-        RollResult {
-            rolls: outcomes,
-            modifier: self.modifier.unwrap_or_default(),
+        Ok(Distribution { probabilities })
+    }
+
+    /// The expected value of the distribution.
+    pub fn mean(&self) -> f64 {
+        self.probabilities
+            .iter()
+            .map(|(&value, &p)| value as f64 * p)
+            .sum()
+    }
+
+    /// The probability of rolling at least `target`.
+    pub fn probability_at_least(&self, target: SignedSides) -> f64 {
+        self.probabilities
+            .range(target..)
+            .map(|(_, &p)| p)
+            .sum()
+    }
+
+    /// Every possible outcome and its probability, in ascending order.
+    pub fn histogram(&self) -> Vec<(SignedSides, f64)> {
+        self.probabilities.iter().map(|(&v, &p)| (v, p)).collect()
+    }
+}
+
+fn group_distribution(group: &DieGroup) -> Result<Distribution, DiceError> {
+    let die = Distribution::uniform_die(group.sides);
+    let mut total = Distribution::point_mass(0);
+    for _ in 0..group.count {
+        total = total.convolve(&die, Op::Add)?;
+    }
+    Ok(total)
+}
+
+/// Errors if `expr` contains a die group with a keep/drop or exploding
+/// modifier, since [`expr_distribution`] can't model those exactly.
+fn check_distribution_supported(expr: &Expr) -> Result<(), DiceError> {
+    match expr {
+        Expr::Group(group) if group.keep.is_some() || group.explode.is_some() => {
+            Err(DiceError::UnsupportedDistribution(group.to_string()))
+        }
+        Expr::Group(_) | Expr::Number(_) | Expr::Variable(_) => Ok(()),
+        Expr::BinOp(l, _, r) => {
+            check_distribution_supported(l)?;
+            check_distribution_supported(r)
+        }
+    }
+}
+
+fn expr_distribution(expr: &Expr, bindings: &Bindings) -> Result<Distribution, DiceError> {
+    match expr {
+        Expr::Number(n) => Ok(Distribution::point_mass(*n)),
+        Expr::Variable(name) => bindings
+            .get(name)
+            .map(|&v| Distribution::point_mass(v))
+            .ok_or_else(|| DiceError::VariableNotFound(name.clone())),
+        Expr::Group(group) => group_distribution(group),
+        Expr::BinOp(l, op, r) => {
+            let left = expr_distribution(l, bindings)?;
+            let right = expr_distribution(r, bindings)?;
+            left.convolve(&right, *op)
         }
     }
 }
@@ -122,89 +655,291 @@ impl FromStr for Dice {
     type Err = DiceError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts = s
-            .split("+")
-            .map(|it| it.trim())
-            .map(|part| -> Result<DiceSpecPart, DiceError> { part.parse() });
-        let mut dice = vec![];
-        let mut total_mod: Option<Modifier> = None;
-        for part in parts {
-            match part {
-                Err(e) => return Err(e),
-                Ok(DiceSpecPart::Die { die, count }) => dice.push((die.sides, count)),
-                Ok(DiceSpecPart::Modifier(m)) => match total_mod {
-                    Some(curr) => total_mod = Some(curr + m),
-                    None => total_mod = Some(m),
-                },
-            }
+        let mut parser = Parser {
+            tokens: tokenize(s),
+            pos: 0,
+        };
+        let expr = parser.parse_expr()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(DiceError::Unparseable(s.into()));
         }
+        Ok(Dice { expr })
+    }
+}
 
-        Ok(Dice {
-            counts: dice,
-            modifier: total_mod,
-        })
+fn format_expr(expr: &Expr) -> String {
+    match expr {
+        Expr::Number(n) => n.to_string(),
+        Expr::Variable(name) => name.clone(),
+        Expr::Group(group) => group.to_string(),
+        Expr::BinOp(l, op, r) => format!("{} {} {}", format_expr(l), op, format_expr(r)),
     }
 }
 
 impl Display for Dice {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}{}",
-            self.counts
-                .iter()
-                .map(|(sides, count)| format!("{}{}", count, Die { sides: *sides }.to_string()))
-                .collect::<Vec<_>>()
-                .join(" + "),
-            if let Some(m) = self.modifier {
-                format!("+ {}", m)
-            } else {
-                "".into()
+        write!(f, "{}", format_expr(&self.expr))
+    }
+}
+
+/// A single die as rolled within a [`RollResult`], recording whether it
+/// triggered an explosion and whether it was kept or dropped.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct RolledDie {
+    die: Die,
+    value: Sides,
+    exploded: bool,
+    dropped: bool,
+}
+
+/// A rolled counterpart of [`Expr`]: dice have already been rolled, so a
+/// group carries its outcomes instead of its spec.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum EvalNode {
+    Number(Modifier),
+    Variable(String, Modifier),
+    Group(Vec<RolledDie>),
+    BinOp(Box<EvalNode>, Op, Box<EvalNode>),
+}
+
+fn eval_node(node: &EvalNode) -> Result<SignedSides, DiceError> {
+    match node {
+        EvalNode::Number(n) => Ok(*n),
+        EvalNode::Variable(_, v) => Ok(*v),
+        EvalNode::Group(rolls) => rolls
+            .iter()
+            .filter(|roll| !roll.dropped)
+            .map(|roll| roll.value as SignedSides)
+            .try_fold(0i32, |acc, v| acc.checked_add(v))
+            .ok_or(DiceError::Overflow),
+        EvalNode::BinOp(l, op, r) => {
+            let (l, r) = (eval_node(l)?, eval_node(r)?);
+            match op {
+                Op::Add => l.checked_add(r).ok_or(DiceError::Overflow),
+                Op::Sub => l.checked_sub(r).ok_or(DiceError::Overflow),
+                Op::Mul => l.checked_mul(r).ok_or(DiceError::Overflow),
+                Op::Div if r == 0 => Err(DiceError::DivisionByZero),
+                Op::Div => l.checked_div(r).ok_or(DiceError::Overflow),
             }
-        )
+        }
+    }
+}
+
+fn format_rolled_die(roll: &RolledDie) -> String {
+    let mut s = format!("({} -> {}", roll.die, roll.value);
+    if roll.exploded {
+        s.push('!');
+    }
+    s.push(')');
+    if roll.dropped {
+        s = format!("{} [dropped]", s);
+    }
+    s
+}
+
+fn format_eval_node(node: &EvalNode) -> String {
+    match node {
+        EvalNode::Number(n) => format!("(modifier -> {})", n),
+        EvalNode::Variable(name, v) => format!("({} -> {})", name, v),
+        EvalNode::Group(rolls) if rolls.is_empty() => "(no dice)".into(),
+        EvalNode::Group(rolls) => rolls
+            .iter()
+            .map(format_rolled_die)
+            .collect::<Vec<_>>()
+            .join(" + "),
+        EvalNode::BinOp(l, op, r) => {
+            format!("{} {} {}", format_eval_node(l), op, format_eval_node(r))
+        }
     }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct RollResult {
-    rolls: Vec<(Die, Sides)>,
-    modifier: Modifier,
+    root: EvalNode,
+    total: SignedSides,
 }
 
 impl RollResult {
     pub fn total(&self) -> SignedSides {
-        let mut sum: SignedSides = 0;
-        for (_, roll) in &self.rolls {
-            sum += *roll as i32;
-        }
-        sum += self.modifier;
-        sum
+        self.total
     }
 }
 
 impl Display for RollResult {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} + (modifier -> {}) = {}",
-            if self.rolls.len() > 0 {
-                self.rolls
-                    .iter()
-                    .map(|(die, value)| format!("({} -> {})", die, value))
-                    .collect::<Vec<_>>()
-                    .join(" + ")
-            } else {
-                "(no dice)".into()
-            },
-            self.modifier,
-            self.total(),
-        )
+        write!(f, "{} = {}", format_eval_node(&self.root), self.total)
+    }
+}
+
+/// A dice-pool roll for storyteller-system-style games: instead of summing,
+/// counts how many dice meet or exceed a `target`, e.g. `5d10t7` rolls five
+/// d10s and counts each result of 7 or more as a success.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Pool {
+    sides: Sides,
+    count: usize,
+    target: Target,
+    /// A die showing its max value explodes, adding another die to the
+    /// pool, same as [`ExplodeRule`] but without a configurable threshold.
+    explode_tens: bool,
+    /// Each die showing a 1 cancels out one success.
+    subtract_ones: bool,
+}
+
+impl FromStr for Pool {
+    type Err = DiceError;
+
+    /// Parses `NdS tT`, e.g. `5d10t7`, optionally followed by `!` (10s
+    /// explode) and/or `b` (1s subtract successes), e.g. `5d10t7!b`.
+    /// Whitespace between the die spec and the target is ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let joined: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        let err = || DiceError::Unparseable(s.into());
+        let d_pos = joined.find('d').ok_or_else(err)?;
+        let count: usize = if d_pos == 0 {
+            1
+        } else {
+            joined[..d_pos].parse().map_err(|_| err())?
+        };
+        let after_d = &joined[d_pos + 1..];
+        let sides_digits: String = after_d.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if sides_digits.is_empty() {
+            return Err(err());
+        }
+        let sides: Sides = sides_digits.parse().map_err(|_| err())?;
+        let rest = &after_d[sides_digits.len()..];
+
+        let rest = rest.strip_prefix('t').ok_or_else(err)?;
+        let target_digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if target_digits.is_empty() {
+            return Err(err());
+        }
+        let target: Target = target_digits.parse().map_err(|_| err())?;
+        let rest = &rest[target_digits.len()..];
+
+        let (explode_tens, rest) = match rest.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, rest),
+        };
+        let (subtract_ones, rest) = match rest.strip_prefix('b') {
+            Some(rest) => (true, rest),
+            None => (false, rest),
+        };
+        if !rest.is_empty() {
+            return Err(err());
+        }
+
+        Ok(Pool {
+            sides,
+            count,
+            target,
+            explode_tens,
+            subtract_ones,
+        })
+    }
+}
+
+impl Pool {
+    /// Rolls the pool using `rng`. A seeded `rng` gives reproducible
+    /// results.
+    pub fn roll_with(&self, rng: &mut impl Rng) -> PoolResult {
+        let die = Die { sides: self.sides };
+        let mut rolls: Vec<PoolDie> = (0..self.count)
+            .map(|_| PoolDie {
+                value: die.roll_with(rng),
+                exploded: false,
+            })
+            .collect();
+
+        if self.explode_tens {
+            let mut i = 0;
+            let mut explosions = 0;
+            while i < rolls.len() {
+                if rolls[i].value == self.sides && explosions < MAX_EXPLOSIONS {
+                    rolls[i].exploded = true;
+                    rolls.push(PoolDie {
+                        value: die.roll_with(rng),
+                        exploded: false,
+                    });
+                    explosions += 1;
+                }
+                i += 1;
+            }
+        }
+
+        PoolResult { pool: *self, rolls }
+    }
+
+    pub fn roll(&self) -> PoolResult {
+        self.roll_with(&mut rand::thread_rng())
+    }
+}
+
+/// A single die as rolled within a [`PoolResult`], recording whether it
+/// triggered a 10s-explode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct PoolDie {
+    value: Sides,
+    exploded: bool,
+}
+
+/// The outcome of rolling a [`Pool`]: every die's value plus the resulting
+/// success count.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolResult {
+    pool: Pool,
+    rolls: Vec<PoolDie>,
+}
+
+impl PoolResult {
+    /// The number of dice meeting or exceeding the pool's target, minus one
+    /// per die showing a 1 if the pool subtracts ones.
+    pub fn successes(&self) -> i32 {
+        let hits = self
+            .rolls
+            .iter()
+            .filter(|roll| roll.value >= self.pool.target)
+            .count() as i32;
+        let botches = if self.pool.subtract_ones {
+            self.rolls.iter().filter(|roll| roll.value == 1).count() as i32
+        } else {
+            0
+        };
+        hits - botches
+    }
+}
+
+fn format_pool_die(die: Die, roll: &PoolDie, target: Target) -> String {
+    let mut s = format!("({} -> {}", die, roll.value);
+    if roll.exploded {
+        s.push('!');
+    }
+    if roll.value >= target {
+        s.push_str(" \u{2713}");
+    }
+    s.push(')');
+    s
+}
+
+impl Display for PoolResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let die = Die {
+            sides: self.pool.sides,
+        };
+        let rolls = self
+            .rolls
+            .iter()
+            .map(|roll| format_pool_die(die, roll, self.pool.target))
+            .collect::<Vec<_>>()
+            .join("");
+        write!(f, "{} = {} successes", rolls, self.successes())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use quickcheck_macros::quickcheck;
+    use rand::{rngs::StdRng, SeedableRng};
 
     use super::*;
     #[test]
@@ -213,8 +948,7 @@ mod tests {
         assert_eq!(
             d,
             Dice {
-                counts: vec![(8, 1)],
-                modifier: None
+                expr: Expr::Group(DieGroup::plain(8, 1)),
             }
         )
     }
@@ -225,8 +959,11 @@ mod tests {
         assert_eq!(
             d,
             Dice {
-                counts: vec![(8, 1), (4, 1)],
-                modifier: None
+                expr: Expr::BinOp(
+                    Box::new(Expr::Group(DieGroup::plain(8, 1))),
+                    Op::Add,
+                    Box::new(Expr::Group(DieGroup::plain(4, 1))),
+                ),
             }
         )
     }
@@ -237,66 +974,268 @@ mod tests {
         assert_eq!(
             d,
             Dice {
-                counts: vec![(4, 2)],
-                modifier: None
+                expr: Expr::Group(DieGroup::plain(4, 2)),
             }
         )
     }
 
     #[test]
-    fn test_parse_multi_die_with_coeff() {
-        let d: Dice = "d8 + 2d4".parse().unwrap();
+    fn test_parse_multi_die_with_modifier() {
+        let d: Dice = "d8 + 2d4 + -7".parse().unwrap();
         assert_eq!(
             d,
             Dice {
-                counts: vec![(8, 1), (4, 2)],
-                modifier: None
+                expr: Expr::BinOp(
+                    Box::new(Expr::BinOp(
+                        Box::new(Expr::Group(DieGroup::plain(8, 1))),
+                        Op::Add,
+                        Box::new(Expr::Group(DieGroup::plain(4, 2))),
+                    )),
+                    Op::Add,
+                    Box::new(Expr::BinOp(
+                        Box::new(Expr::Number(0)),
+                        Op::Sub,
+                        Box::new(Expr::Number(7)),
+                    )),
+                ),
             }
         )
     }
 
     #[test]
-    fn test_parse_multi_die_with_modifier() {
-        let d: Dice = "d8 + 2d4 + -7".parse().unwrap();
+    fn test_modifier_only() {
+        let d: Dice = "9".parse().unwrap();
         assert_eq!(
             d,
             Dice {
-                counts: vec![(8, 1), (4, 2)],
-                modifier: Some(-7)
+                expr: Expr::Number(9),
             }
         )
     }
 
     #[test]
-    fn test_parse_multi_die_with_modifiers() {
-        let d: Dice = "d8 + 3 + 2d4 + -7".parse().unwrap();
+    fn test_parse_keep_highest() {
+        let d: Dice = "4d6kh3".parse().unwrap();
         assert_eq!(
             d,
             Dice {
-                counts: vec![(8, 1), (4, 2)],
-                modifier: Some(-4)
+                expr: Expr::Group(DieGroup {
+                    sides: 6,
+                    count: 4,
+                    keep: Some(KeepRule::KeepHighest(3)),
+                    explode: None,
+                }),
             }
         )
     }
 
     #[test]
-    fn test_modifier_only() {
-        let d: Dice = "9".parse().unwrap();
+    fn test_parse_explode_with_threshold() {
+        let d: Dice = "d10!>8".parse().unwrap();
         assert_eq!(
             d,
             Dice {
-                counts: vec![],
-                modifier: Some(9)
+                expr: Expr::Group(DieGroup {
+                    sides: 10,
+                    count: 1,
+                    keep: None,
+                    explode: Some(ExplodeRule { threshold: Some(8) }),
+                }),
             }
         )
     }
 
+    #[test]
+    fn test_roll_keep_highest_drops_the_rest() {
+        let d: Dice = "4d6kh3".parse().unwrap();
+        let r = d.roll(&Bindings::new()).unwrap();
+        match &r.root {
+            EvalNode::Group(rolls) => {
+                assert_eq!(rolls.len(), 4);
+                assert_eq!(rolls.iter().filter(|roll| roll.dropped).count(), 1);
+            }
+            _ => panic!("expected a single group"),
+        }
+    }
+
+    #[test]
+    fn test_roll_explode_is_capped() {
+        let d: Dice = "d1!".parse().unwrap();
+        let r = d.roll(&Bindings::new()).unwrap();
+        match &r.root {
+            EvalNode::Group(rolls) => assert_eq!(rolls.len(), MAX_EXPLOSIONS + 1),
+            _ => panic!("expected a single group"),
+        }
+    }
+
+    #[test]
+    fn test_parse_subtraction() {
+        let d: Dice = "10 - 3".parse().unwrap();
+        assert_eq!(d.roll(&Bindings::new()).unwrap().total(), 7);
+    }
+
+    #[test]
+    fn test_parse_multiplication() {
+        let d: Dice = "2 * 4".parse().unwrap();
+        assert_eq!(d.roll(&Bindings::new()).unwrap().total(), 8);
+    }
+
+    #[test]
+    fn test_parse_division() {
+        let d: Dice = "9 / 2".parse().unwrap();
+        assert_eq!(d.roll(&Bindings::new()).unwrap().total(), 4);
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        let d: Dice = "2 + 3 * 4".parse().unwrap();
+        assert_eq!(d.roll(&Bindings::new()).unwrap().total(), 14);
+    }
+
+    #[test]
+    fn test_parens_override_precedence() {
+        let d: Dice = "(2 + 3) * 4".parse().unwrap();
+        assert_eq!(d.roll(&Bindings::new()).unwrap().total(), 20);
+    }
+
+    #[test]
+    fn test_subtraction_is_left_associative() {
+        let d: Dice = "10 - 2 - 3".parse().unwrap();
+        assert_eq!(d.roll(&Bindings::new()).unwrap().total(), 5);
+    }
+
+    #[test]
+    fn test_parse_variable() {
+        let d: Dice = "d20 + STR".parse().unwrap();
+        assert_eq!(
+            d,
+            Dice {
+                expr: Expr::BinOp(
+                    Box::new(Expr::Group(DieGroup::plain(20, 1))),
+                    Op::Add,
+                    Box::new(Expr::Variable("STR".into())),
+                ),
+            }
+        )
+    }
+
+    #[test]
+    fn test_roll_resolves_variable() {
+        let d: Dice = "d20 + STR".parse().unwrap();
+        let bindings = Bindings::from([("STR".to_string(), 3)]);
+        let r = d.roll(&bindings).unwrap();
+        assert!(r.total() >= 4 && r.total() <= 23);
+    }
+
+    #[test]
+    fn test_roll_unbound_variable_errors() {
+        let d: Dice = "d20 + STR".parse().unwrap();
+        match d.roll(&Bindings::new()) {
+            Err(DiceError::VariableNotFound(name)) => assert_eq!(name, "STR"),
+            other => panic!("expected VariableNotFound, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_roll_division_by_zero_errors() {
+        let d: Dice = "d20 / 0".parse().unwrap();
+        match d.roll(&Bindings::new()) {
+            Err(DiceError::DivisionByZero) => {}
+            other => panic!("expected DivisionByZero, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_roll_division_by_zero_from_dice_errors() {
+        // Uses 10 for the numerator, and a difference of two d6 (which
+        // ties, and so divides by zero, about 1/6 of the time) for the
+        // denominator.
+        let d: Dice = "10 / (d6 - d6)".parse().unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        match d.roll_with(&Bindings::new(), &mut rng) {
+            Err(DiceError::DivisionByZero) => {}
+            other => panic!("expected DivisionByZero, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_roll_multiplication_overflow_errors() {
+        let d: Dice = "5 * 1000000000".parse().unwrap();
+        match d.roll(&Bindings::new()) {
+            Err(DiceError::Overflow) => {}
+            other => panic!("expected Overflow, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_roll_addition_overflow_errors() {
+        let d: Dice = "2000000000 + 2000000000".parse().unwrap();
+        match d.roll(&Bindings::new()) {
+            Err(DiceError::Overflow) => {}
+            other => panic!("expected Overflow, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_roll_division_overflow_errors() {
+        // i32::MIN / -1 overflows, since i32::MAX can't represent 2^31.
+        let d: Dice = "(0 - 2147483647 - 1) / (0 - 1)".parse().unwrap();
+        match d.roll(&Bindings::new()) {
+            Err(DiceError::Overflow) => {}
+            other => panic!("expected Overflow, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_distribution_resolves_variable() {
+        let d: Dice = "d4 + prof".parse().unwrap();
+        let bindings = Bindings::from([("prof".to_string(), 2)]);
+        let dist = d.distribution(&bindings).unwrap();
+        assert!(approx_eq(dist.mean(), 2.5 + 2.0));
+    }
+
+    #[test]
+    fn test_distribution_rejects_keep_drop() {
+        let d: Dice = "4d6kh3".parse().unwrap();
+        match d.distribution(&Bindings::new()) {
+            Err(DiceError::UnsupportedDistribution(_)) => {}
+            other => panic!("expected UnsupportedDistribution, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_distribution_rejects_explode() {
+        let d: Dice = "d6!".parse().unwrap();
+        match d.distribution(&Bindings::new()) {
+            Err(DiceError::UnsupportedDistribution(_)) => {}
+            other => panic!("expected UnsupportedDistribution, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_distribution_division_by_zero_errors() {
+        let d: Dice = "10 / (d6 - d6)".parse().unwrap();
+        match d.distribution(&Bindings::new()) {
+            Err(DiceError::DivisionByZero) => {}
+            other => panic!("expected DivisionByZero, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_distribution_overflow_errors() {
+        let d: Dice = "2000000000 + 2000000000".parse().unwrap();
+        match d.distribution(&Bindings::new()) {
+            Err(DiceError::Overflow) => {}
+            other => panic!("expected Overflow, got {:?}", other),
+        }
+    }
+
     #[quickcheck]
     fn single_roll_range(sides: Sides) -> bool {
         if sides > 0 {
             let s = format!("d{}", sides);
             let d: Dice = s.parse().unwrap();
-            let t = d.roll().total();
+            let t = d.roll(&Bindings::new()).unwrap().total();
             t <= sides.into() && t > 0
         } else {
             true
@@ -308,7 +1247,7 @@ mod tests {
         if sides > 0 && coeff > 0 && coeff < 1000 {
             let s = format!("{}d{}", coeff, sides);
             let d: Dice = s.parse().unwrap();
-            let t = d.roll().total();
+            let t = d.roll(&Bindings::new()).unwrap().total();
             t <= sides as i32 * coeff as i32 && t > 0
         } else {
             true
@@ -320,7 +1259,7 @@ mod tests {
         if sides > 0 && sides_2 > 0 && coeff > 0 && coeff < 1000 {
             let s = format!("{}d{} + d{}", coeff, sides, sides_2);
             let d: Dice = s.parse().unwrap();
-            let t = d.roll().total();
+            let t = d.roll(&Bindings::new()).unwrap().total();
             t <= sides as i32 * coeff as i32 + sides_2 as i32 && t > 0
         } else {
             true
@@ -332,10 +1271,174 @@ mod tests {
         if sides > 0 && sides_2 > 0 && coeff > 0 && coeff < 1000 && modifier.abs() < 10000 {
             let s = format!("{}d{} + d{} + {}", coeff, sides, sides_2, modifier);
             let d: Dice = s.parse().unwrap();
-            let t = d.roll().total();
+            let t = d.roll(&Bindings::new()).unwrap().total();
             t <= modifier + sides as i32 * coeff as i32 + sides_2 as i32 && t > modifier
         } else {
             true
         }
     }
+
+    #[quickcheck]
+    fn subtraction_range(sides: Sides, sub: u16) -> bool {
+        if sides > 0 && (sub as i32) < 10000 {
+            let s = format!("d{} - {}", sides, sub);
+            let d: Dice = s.parse().unwrap();
+            let t = d.roll(&Bindings::new()).unwrap().total();
+            t <= sides as i32 - sub as i32 && t > -(sub as i32)
+        } else {
+            true
+        }
+    }
+
+    #[quickcheck]
+    fn multiplication_range(sides: Sides, factor: u16) -> bool {
+        if sides > 0 && factor > 0 && factor < 1000 {
+            let s = format!("d{} * {}", sides, factor);
+            let d: Dice = s.parse().unwrap();
+            let t = d.roll(&Bindings::new()).unwrap().total();
+            t <= sides as i32 * factor as i32 && t > 0
+        } else {
+            true
+        }
+    }
+
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_distribution_single_die() {
+        let d: Dice = "d6".parse().unwrap();
+        let dist = d.distribution(&Bindings::new()).unwrap();
+        let hist = dist.histogram();
+        assert_eq!(hist.len(), 6);
+        for (_, p) in &hist {
+            assert!(approx_eq(*p, 1.0 / 6.0));
+        }
+        assert!(approx_eq(dist.mean(), 3.5));
+    }
+
+    #[test]
+    fn test_distribution_modifier_only() {
+        let d: Dice = "9".parse().unwrap();
+        let dist = d.distribution(&Bindings::new()).unwrap();
+        assert_eq!(dist.histogram(), vec![(9, 1.0)]);
+    }
+
+    #[test]
+    fn test_distribution_two_dice_mean() {
+        let d: Dice = "2d6".parse().unwrap();
+        let dist = d.distribution(&Bindings::new()).unwrap();
+        assert!(approx_eq(dist.mean(), 7.0));
+        assert_eq!(dist.histogram().len(), 11); // sums 2..=12
+    }
+
+    #[test]
+    fn test_probability_at_least() {
+        let d: Dice = "d20".parse().unwrap();
+        let dist = d.distribution(&Bindings::new()).unwrap();
+        assert!(approx_eq(dist.probability_at_least(15), 6.0 / 20.0));
+        assert!(approx_eq(dist.probability_at_least(21), 0.0));
+        assert!(approx_eq(dist.probability_at_least(1), 1.0));
+    }
+
+    #[quickcheck]
+    fn distribution_sums_to_one(sides: Sides) -> bool {
+        if sides > 0 && sides < 1000 {
+            let s = format!("d{}", sides);
+            let d: Dice = s.parse().unwrap();
+            let dist = d.distribution(&Bindings::new()).unwrap();
+            let total: f64 = dist.histogram().iter().map(|(_, p)| *p).sum();
+            approx_eq(total, 1.0)
+        } else {
+            true
+        }
+    }
+
+    #[test]
+    fn test_parse_pool() {
+        let p: Pool = "5d10t7".parse().unwrap();
+        assert_eq!(
+            p,
+            Pool {
+                sides: 10,
+                count: 5,
+                target: 7,
+                explode_tens: false,
+                subtract_ones: false,
+            }
+        )
+    }
+
+    #[test]
+    fn test_parse_pool_with_flags() {
+        let p: Pool = "5d10t7!b".parse().unwrap();
+        assert_eq!(
+            p,
+            Pool {
+                sides: 10,
+                count: 5,
+                target: 7,
+                explode_tens: true,
+                subtract_ones: true,
+            }
+        )
+    }
+
+    #[test]
+    fn test_parse_pool_ignores_whitespace() {
+        let p: Pool = "5d10 t7".parse().unwrap();
+        assert_eq!(p.count, 5);
+        assert_eq!(p.target, 7);
+    }
+
+    #[test]
+    fn test_roll_pool_counts_successes() {
+        let p: Pool = "5d10t11".parse().unwrap(); // no d10 can hit an 11
+        let r = p.roll();
+        assert_eq!(r.rolls.len(), 5);
+        assert_eq!(r.successes(), 0);
+    }
+
+    #[test]
+    fn test_roll_pool_all_successes() {
+        let p: Pool = "5d10t1".parse().unwrap(); // every d10 hits at least 1
+        let r = p.roll();
+        assert_eq!(r.successes(), 5);
+    }
+
+    #[test]
+    fn test_roll_pool_subtract_ones_can_go_negative() {
+        let p: Pool = "5d10t11b".parse().unwrap(); // no successes, every 1 botches
+        let r = p.roll();
+        let ones = r.rolls.iter().filter(|roll| roll.value == 1).count() as i32;
+        assert_eq!(r.successes(), -ones);
+    }
+
+    #[test]
+    fn test_roll_pool_explode_tens_adds_dice() {
+        let p: Pool = "1d1t1!".parse().unwrap();
+        let r = p.roll();
+        assert_eq!(r.rolls.len(), MAX_EXPLOSIONS + 1);
+    }
+
+    #[test]
+    fn test_seeded_roll_is_deterministic() {
+        let d: Dice = "3d6".parse().unwrap();
+        let mut rng_a = StdRng::seed_from_u64(42);
+        let mut rng_b = StdRng::seed_from_u64(42);
+        let a = d.roll_with(&Bindings::new(), &mut rng_a).unwrap();
+        let b = d.roll_with(&Bindings::new(), &mut rng_b).unwrap();
+        assert_eq!(a.total(), b.total());
+    }
+
+    #[test]
+    fn test_seeded_pool_roll_is_deterministic() {
+        let p: Pool = "5d10t7".parse().unwrap();
+        let mut rng_a = StdRng::seed_from_u64(7);
+        let mut rng_b = StdRng::seed_from_u64(7);
+        let a = p.roll_with(&mut rng_a);
+        let b = p.roll_with(&mut rng_b);
+        assert_eq!(a.successes(), b.successes());
+    }
 }